@@ -0,0 +1,192 @@
+use {Guid, Result, ucs2};
+use core::mem;
+use core::slice;
+use core::str;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use super::FileAttribute;
+
+/// A UEFI `EFI_TIME` structure, used to timestamp file create/access/modify
+/// events.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Time {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    _pad1: u8,
+    nanosecond: u32,
+    time_zone: i16,
+    daylight: u8,
+    _pad2: u8,
+}
+
+impl Time {
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    pub fn nanosecond(&self) -> u32 {
+        self.nanosecond
+    }
+
+    pub fn time_zone(&self) -> i16 {
+        self.time_zone
+    }
+
+    pub fn daylight(&self) -> u8 {
+        self.daylight
+    }
+}
+
+/// A data structure that can be queried/set through `FileHandle::get_info`/`FileHandle::set_info`.
+///
+/// Each implementor of this trait is identified by a GUID which is passed
+/// alongside the raw buffer to the firmware's `EFI_FILE_PROTOCOL.GetInfo`/`SetInfo`
+/// calls.
+pub trait FileProtocolInfo {
+    #[doc(hidden)]
+    fn guid() -> &'static Guid;
+
+    #[doc(hidden)]
+    unsafe fn from_buffer(buffer: Vec<u64>, byte_len: usize) -> Box<Self>;
+}
+
+// The fixed-size portion of `EFI_FILE_INFO`, used only to compute the offset
+// of the variable-length `FileName` field that follows it.
+#[repr(C)]
+struct FileInfoHeader {
+    size: u64,
+    file_size: u64,
+    physical_size: u64,
+    create_time: Time,
+    last_access_time: Time,
+    modification_time: Time,
+    attribute: FileAttribute,
+}
+
+/// The `EFI_FILE_INFO` structure, as returned by `FileHandle::get_info::<FileInfo>()`.
+///
+/// This type is unsized: its `FileName` field is a variable-length,
+/// null-terminated UCS-2 string that immediately follows the fixed-size
+/// header, so it can only be handled behind a pointer (`&FileInfo`, `Box<FileInfo>`).
+#[repr(C)]
+pub struct FileInfo {
+    size: u64,
+    file_size: u64,
+    physical_size: u64,
+    create_time: Time,
+    last_access_time: Time,
+    modification_time: Time,
+    attribute: FileAttribute,
+    file_name: [u16],
+}
+
+impl FileInfo {
+    /// Returns the size of this `EFI_FILE_INFO` structure, including the variable-length `FileName`
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the size of the file, in bytes
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /// Returns the amount of physical space the file consumes on the device, in bytes
+    pub fn physical_size(&self) -> u64 {
+        self.physical_size
+    }
+
+    /// Returns the time the file was created
+    pub fn create_time(&self) -> &Time {
+        &self.create_time
+    }
+
+    /// Returns the time the file was last accessed
+    pub fn last_access_time(&self) -> &Time {
+        &self.last_access_time
+    }
+
+    /// Returns the time the file was last modified
+    pub fn modification_time(&self) -> &Time {
+        &self.modification_time
+    }
+
+    /// Returns the attributes of this file, as used by `FileMode::CREATE`
+    pub fn attribute(&self) -> FileAttribute {
+        self.attribute
+    }
+
+    /// Borrows a `FileInfo` out of the raw `EFI_FILE_INFO` bytes in `buf`, as filled
+    /// in by a prior `read` call on a directory handle
+    ///
+    /// `buf` must hold exactly one `EFI_FILE_INFO` record, as returned by
+    /// `Directory::read_entry`.
+    pub(crate) unsafe fn from_bytes(buf: &[u8]) -> &FileInfo {
+        let name_len = (buf.len() - mem::size_of::<FileInfoHeader>()) / mem::size_of::<u16>();
+        // The fat pointer's data component must address the start of the struct, not
+        // the tail field, so the compiler can add each fixed field's offset from it.
+        let fat_slice = slice::from_raw_parts(buf.as_ptr() as *const u16, name_len);
+
+        &*(fat_slice as *const [u16] as *const FileInfo)
+    }
+
+    /// Decodes the UCS-2 `FileName` field into `buf`, returning the decoded `&str`
+    ///
+    /// # Errors
+    /// * `uefi::Status::BufferTooSmall`   The provided buffer was too small to hold the name
+    pub fn file_name<'buf>(&self, buf: &'buf mut [u8]) -> Result<&'buf str> {
+        let len = ucs2::decode_ucs2(&self.file_name, buf)?;
+        Ok(unsafe { str::from_utf8_unchecked(&buf[..len]) })
+    }
+}
+
+impl FileProtocolInfo for FileInfo {
+    fn guid() -> &'static Guid {
+        static FILE_INFO_GUID: Guid = Guid::from_values(
+            0x09576e92,
+            0x6d3f,
+            0x11d2,
+            [0x8e, 0x39, 0x00, 0xa0, 0xc9, 0x69, 0x72, 0x3b],
+        );
+
+        &FILE_INFO_GUID
+    }
+
+    unsafe fn from_buffer(buffer: Vec<u64>, byte_len: usize) -> Box<Self> {
+        let name_len = (byte_len - mem::size_of::<FileInfoHeader>()) / mem::size_of::<u16>();
+
+        let raw_ptr = Box::into_raw(buffer.into_boxed_slice()) as *mut u8;
+        // The fat pointer's data component must address the start of the struct, not
+        // the tail field, so the compiler can add each fixed field's offset from it.
+        let fat_slice = slice::from_raw_parts_mut(raw_ptr as *mut u16, name_len);
+        let fat_ptr = fat_slice as *mut [u16] as *mut FileInfo;
+
+        Box::from_raw(fat_ptr)
+    }
+}
@@ -1,5 +1,13 @@
-use {Status, Result, ucs2};
+use {Guid, Status, Result, ucs2};
 use core::mem;
+use core::ptr;
+use core::ops::{Deref, DerefMut};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+mod file_info;
+pub use self::file_info::{FileInfo, FileProtocolInfo, Time};
 
 bitflags! {
     pub struct FileMode : u64 {
@@ -32,8 +40,8 @@ pub struct FileImpl {
     write: extern "C" fn(this: &mut FileImpl, buffer_size: &mut usize, buffer: *const u8) -> Status,
     get_position: extern "C" fn(this: &mut FileImpl, position: &mut u64) -> Status,
     set_position: extern "C" fn(this: &mut FileImpl, position: u64) -> Status,
-    get_info: usize,
-    set_info: usize,
+    get_info: extern "C" fn(this: &mut FileImpl, information_type: *const Guid, buffer_size: &mut usize, buffer: *mut u8) -> Status,
+    set_info: extern "C" fn(this: &mut FileImpl, information_type: *const Guid, buffer_size: usize, buffer: *const u8) -> Status,
     flush: extern "C" fn(this: &mut FileImpl) -> Status,
 }
 
@@ -43,11 +51,18 @@ pub struct SimpleFileSystem {
     open_volume: extern "C" fn(this: &mut SimpleFileSystem, root: &mut usize) -> Status, 
 }
 
-pub struct File<'a> {
+/// A handle to an open file or directory
+///
+/// Since UEFI doesn't distinguish between file and directory handles until
+/// `EFI_FILE_INFO` is consulted, a handle returned by `open` is always a plain
+/// `FileHandle` (only `SimpleFileSystem::open_volume` returns a `Directory`). Call `into_type` to find out which kind of node it is and
+/// get back a `RegularFile` or `Directory` with the operations specific to
+/// that kind.
+pub struct FileHandle<'a> {
     inner: &'a mut FileImpl,
 }
 
-impl<'a> File<'a> {
+impl<'a> FileHandle<'a> {
     /// Try to open a file relative to this file/directory.
     ///
     /// # Arguments
@@ -55,7 +70,7 @@ impl<'a> File<'a> {
     /// * `open_mode`   The mode to open the file with. Valid
     ///     combinations are READ, READ | WRITE and READ | WRITE | CREATE
     /// * `attributes`  Only valid when FILE_MODE_CREATE is used as a mode
-    /// 
+    ///
     /// # Errors
     /// * `uefi::Status::InvalidParameter`  The filename exceeds the maximum length of 255 chars
     /// * `uefi::Status::NotFound`          Could not find file
@@ -67,7 +82,7 @@ impl<'a> File<'a> {
     /// * `uefi::Status::AccessDenied`      The service denied access to the file
     /// * `uefi::Status::OutOfResources`    Not enough resources to open file
     /// * `uefi::Status::VolumeFull`        The volume is full
-    pub fn open(&mut self, filename: &str, open_mode: FileMode, attributes: FileAttribute) -> Result<File> {
+    pub fn open(&mut self, filename: &str, open_mode: FileMode, attributes: FileAttribute) -> Result<FileHandle> {
         const BUF_SIZE : usize = 255;
         if filename.len() > BUF_SIZE {
             Err(Status::InvalidParameter)
@@ -77,12 +92,30 @@ impl<'a> File<'a> {
             let mut ptr = 0usize;
 
             ucs2::encode_ucs2(filename, &mut buf)?;
-            (self.inner.open)(self.inner, &mut ptr, buf.as_ptr(), open_mode, attributes).into_with(|| File {
+            (self.inner.open)(self.inner, &mut ptr, buf.as_ptr(), open_mode, attributes).into_with(|| FileHandle {
                 inner: unsafe { &mut *(ptr as *mut FileImpl) }
             })
         }
     }
 
+    /// Inspects this handle's `FileInfo` to find out whether it is a regular
+    /// file or a directory, and returns the appropriately-typed handle
+    ///
+    /// # Errors
+    /// * `uefi::Status::NoMedia`           The device has no media
+    /// * `uefi::Status::DeviceError`       The device reported an error
+    /// * `uefi::Status::VolumeCorrupted`   The filesystem structures are corrupted
+    pub fn into_type(mut self) -> Result<FileType<'a>> {
+        let is_dir = self.get_info::<FileInfo>()?.attribute().contains(FileAttribute::DIRECTORY);
+
+        Ok(if is_dir {
+            FileType::Dir(Directory(self))
+        }
+        else {
+            FileType::Regular(RegularFile(self))
+        })
+    }
+
     /// Close this file handle
     ///
     /// This MUST be called when you are done with the file
@@ -102,16 +135,25 @@ impl<'a> File<'a> {
     ///
     /// Try to read as much as possible into `buffer`. Returns the number of bytes read
     ///
+    /// If `buffer` is not large enough to hold the next pending record (relevant
+    /// for reading directory entries, where records have variable length),
+    /// `Err(Some(required_size))` is returned with the size `buffer` needs to be.
+    /// A genuine device error instead carries `None`.
+    ///
     /// # Arguments
     /// * `buffer`  The target buffer of the read operation
     ///
     /// # Errors
     /// * `uefi::Status::NoMedia`           The device has no media
-    /// * `uefi::Status::DeviceError`       The device reported an error 
+    /// * `uefi::Status::DeviceError`       The device reported an error
     /// * `uefi::Status::VolumeCorrupted`   The filesystem structures are corrupted
-    pub fn read(&mut self, buffer: &mut[u8]) -> Result<usize> {
+    pub fn read(&mut self, buffer: &mut[u8]) -> ::core::result::Result<usize, Option<usize>> {
         let mut buffer_size = buffer.len();
-        (self.inner.read)(self.inner, &mut buffer_size, buffer.as_mut_ptr()).into_with(|| buffer_size)
+        match (self.inner.read)(self.inner, &mut buffer_size, buffer.as_mut_ptr()) {
+            Status::Success => Ok(buffer_size),
+            Status::BufferTooSmall => Err(Some(buffer_size)),
+            _ => Err(None),
+        }
     }
 
     /// Write data to file
@@ -157,6 +199,52 @@ impl<'a> File<'a> {
         (self.inner.set_position)(self.inner, position).into()
     }
 
+    /// Queries information about this file
+    ///
+    /// The information is returned in the form best suited to this call,
+    /// e.g. `FileHandle::get_info::<FileInfo>()` decodes the `EFI_FILE_INFO` the
+    /// firmware hands back into a `Box<FileInfo>`.
+    ///
+    /// This performs the spec's two-call dance: probe with a zero-size buffer
+    /// to learn the required size, allocate that much (rounded up to a multiple
+    /// of 8 bytes for alignment), then retry.
+    ///
+    /// # Errors
+    /// * `uefi::Status::NoMedia`           The device has no media
+    /// * `uefi::Status::DeviceError`       The device reported an error
+    /// * `uefi::Status::VolumeCorrupted`   The filesystem structures are corrupted
+    pub fn get_info<Info: FileProtocolInfo + ?Sized>(&mut self) -> Result<Box<Info>> {
+        let mut buffer_size = 0usize;
+        match (self.inner.get_info)(self.inner, Info::guid(), &mut buffer_size, ptr::null_mut()) {
+            Status::BufferTooSmall => {},
+            other => return other.into(),
+        }
+
+        let mut buffer: Vec<u64> = vec![0u64; (buffer_size + 7) / 8];
+        let buf_ptr = buffer.as_mut_ptr() as *mut u8;
+
+        (self.inner.get_info)(self.inner, Info::guid(), &mut buffer_size, buf_ptr)
+            .into_with(|| unsafe { Info::from_buffer(buffer, buffer_size) })
+    }
+
+    /// Sets information about this file
+    ///
+    /// # Arguments
+    /// * `info`    The new information to apply to this file
+    ///
+    /// # Errors
+    /// * `uefi::Status::NoMedia`           The device has no media
+    /// * `uefi::Status::DeviceError`       The device reported an error
+    /// * `uefi::Status::VolumeCorrupted`   The filesystem structures are corrupted
+    /// * `uefi::Status::WriteProtected`    Attempt to set information on a readonly file
+    /// * `uefi::Status::AccessDenied`      The file was opened read only
+    pub fn set_info<Info: FileProtocolInfo + ?Sized>(&mut self, info: &Info) -> Result<()> {
+        let buffer_size = mem::size_of_val(info);
+        let buffer = info as *const Info as *const u8;
+
+        (self.inner.set_info)(self.inner, Info::guid(), buffer_size, buffer).into()
+    }
+
     /// Flushes all modified data associated with the file handle to the device
     ///
     /// # Errors
@@ -169,6 +257,50 @@ impl<'a> File<'a> {
     pub fn flush(&mut self) -> Result<()> {
         (self.inner.flush)(self.inner).into()
     }
+
+    /// Seeks to a new position in the file, relative to the start, the end,
+    /// or the current position
+    ///
+    /// Returns the resulting absolute position
+    ///
+    /// # Arguments
+    /// * `from` The position to seek to
+    ///
+    /// # Errors
+    /// * `uefi::Status::DeviceError`       An attempt was made to set the position of a deleted file
+    /// * `uefi::Status::InvalidParameter`  The resulting position would be before the start of the file
+    pub fn seek(&mut self, from: SeekFrom) -> Result<u64> {
+        let new_position = match from {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(delta) => offset_by(self.get_position()?, delta)?,
+            SeekFrom::End(delta) => offset_by(self.get_info::<FileInfo>()?.file_size(), delta)?,
+        };
+
+        self.set_position(new_position)?;
+        Ok(new_position)
+    }
+}
+
+/// Applies a signed offset to an absolute position, erroring out if the
+/// result would lie before the start of the file
+fn offset_by(base: u64, delta: i64) -> Result<u64> {
+    let target = base as i128 + delta as i128;
+    if target < 0 || target > u64::max_value() as i128 {
+        Err(Status::InvalidParameter)
+    }
+    else {
+        Ok(target as u64)
+    }
+}
+
+/// The position to seek to with `FileHandle::seek`
+pub enum SeekFrom {
+    /// Seek to an absolute offset from the start of the file
+    Start(u64),
+    /// Seek to an offset relative to the current position
+    Current(i64),
+    /// Seek to an offset relative to the end of the file
+    End(i64),
 }
 
 impl SimpleFileSystem {
@@ -182,12 +314,152 @@ impl SimpleFileSystem {
     /// * `uefi::Status::AccessDenied`  The service denied access to the file
     /// * `uefi::Status::OutOfResources`    The volume was not opened
     /// * `uefi::Status::MediaChanged`  The device has a different medium in it
-    pub fn open_volume(&mut self) -> Result<File> {
+    pub fn open_volume(&mut self) -> Result<Directory> {
         let mut ptr = 0usize;
-        (self.open_volume)(self, &mut ptr).into_with(|| File { inner: unsafe { &mut *(ptr as *mut FileImpl)} })
+        (self.open_volume)(self, &mut ptr).into_with(|| Directory(FileHandle { inner: unsafe { &mut *(ptr as *mut FileImpl)} }))
+    }
+}
+
+/// A file that has been identified as a regular, non-directory file
+pub struct RegularFile<'a>(FileHandle<'a>);
+
+impl<'a> Deref for RegularFile<'a> {
+    type Target = FileHandle<'a>;
+
+    fn deref(&self) -> &FileHandle<'a> {
+        &self.0
     }
 }
 
+impl<'a> DerefMut for RegularFile<'a> {
+    fn deref_mut(&mut self) -> &mut FileHandle<'a> {
+        &mut self.0
+    }
+}
+
+/// A file that has been identified as a directory
+///
+/// Unlike a `RegularFile`, a `Directory`'s `read` calls are overloaded by the
+/// spec to return one `EFI_FILE_INFO` record per call; use `read_entry` to
+/// iterate over a directory's contents instead of calling `read` directly.
+pub struct Directory<'a>(FileHandle<'a>);
+
+impl<'a> Directory<'a> {
+    /// Read the next directory entry
+    ///
+    /// Try to read the next directory entry into `buf`. If the buffer is not
+    /// large enough to hold the entry, `Err(Some(required_size))` is returned
+    /// with the size that's needed. A zero-length read indicates the end of
+    /// the directory, in which case `Ok(None)` is returned.
+    ///
+    /// # Arguments
+    /// * `buf`  The target buffer of the read operation, holding the raw `EFI_FILE_INFO` on success
+    ///
+    /// # Errors
+    /// * `uefi::Status::NoMedia`           The device has no media
+    /// * `uefi::Status::DeviceError`       The device reported an error
+    /// * `uefi::Status::VolumeCorrupted`   The filesystem structures are corrupted
+    pub fn read_entry<'buf>(&mut self, buf: &'buf mut [u8]) -> ::core::result::Result<Option<&'buf FileInfo>, Option<usize>> {
+        match self.0.read(buf)? {
+            0 => Ok(None),
+            len => Ok(Some(unsafe { FileInfo::from_bytes(&buf[..len]) })),
+        }
+    }
+
+    /// Recursively deletes this directory and everything in it
+    ///
+    /// Walks the directory depth-first: subdirectories are fully emptied
+    /// (via a recursive call) before being deleted themselves, and plain
+    /// files are deleted as they are found. The `.` and `..` entries are
+    /// skipped so the walk doesn't loop back on itself.
+    ///
+    /// If an entry can't be opened or deleted, the first such error is
+    /// remembered and returned once the rest of the traversal (including the
+    /// deletion of this directory itself) has been attempted.
+    ///
+    /// # Errors
+    /// * `uefi::Status::DeviceError`    The device reported an error while reading or deleting an entry
+    /// * `uefi::Status::AccessDenied`   The service denied access to an entry
+    pub fn remove_all(mut self) -> Result<()> {
+        let mut first_error: Option<Status> = None;
+        let mut entry_buf: Vec<u8> = vec![0u8; 384];
+
+        loop {
+            let entry = loop {
+                match self.read_entry(&mut entry_buf) {
+                    Ok(entry) => break entry,
+                    Err(Some(required_size)) => entry_buf = vec![0u8; required_size],
+                    Err(None) => {
+                        first_error.get_or_insert(Status::DeviceError);
+                        break None;
+                    },
+                }
+            };
+
+            let info = match entry {
+                Some(info) => info,
+                None => break,
+            };
+
+            let mut name_buf = [0u8; 255];
+            let name = match info.file_name(&mut name_buf) {
+                Ok(name) => name,
+                Err(status) => {
+                    first_error.get_or_insert(status);
+                    continue;
+                },
+            };
+
+            if name == "." || name == ".." {
+                continue;
+            }
+
+            // Decide from the entry we already read, rather than re-querying via
+            // `into_type`, so there's no point between opening the child and
+            // knowing what to do with it where a failure could leak the handle.
+            let is_dir = info.attribute().contains(FileAttribute::DIRECTORY);
+
+            let result = match self.open(name, FileMode::READ | FileMode::WRITE, FileAttribute::NONE) {
+                Ok(handle) => if is_dir { Directory(handle).remove_all() } else { handle.delete() },
+                Err(status) => Err(status),
+            };
+
+            if let Err(status) = result {
+                first_error.get_or_insert(status);
+            }
+        }
+
+        let delete_result = self.0.delete();
+
+        match first_error {
+            Some(status) => Err(status),
+            None => delete_result,
+        }
+    }
+}
+
+impl<'a> Deref for Directory<'a> {
+    type Target = FileHandle<'a>;
+
+    fn deref(&self) -> &FileHandle<'a> {
+        &self.0
+    }
+}
+
+impl<'a> DerefMut for Directory<'a> {
+    fn deref_mut(&mut self) -> &mut FileHandle<'a> {
+        &mut self.0
+    }
+}
+
+/// The type of a file handle, as determined by `FileHandle::into_type`
+pub enum FileType<'a> {
+    /// The file handle is a regular, non-directory file
+    Regular(RegularFile<'a>),
+    /// The file handle is a directory
+    Dir(Directory<'a>),
+}
+
 impl_proto! {
     protocol SimpleFileSystem {
         GUID = 0x0964e5b22,0x6459,0x11d2,[0x8e,0x39,0x00,0xa0,0xc9,0x69,0x72,0x3b];